@@ -1,6 +1,7 @@
 use crate::data_loader;
+use crate::error::Error;
 use ndarray::{Array1, Array2, Axis};
-use rusqlite::{Connection, Error};
+use rusqlite::Connection;
 use std::collections::HashMap;
 
 /// Prepares deck data for clustering by converting it into a multi-hot encoded matrix.
@@ -55,7 +56,7 @@ pub fn prepare_data_for_clustering(db_path: &str) -> Result<(Array2<f64>, Vec<St
     // We need to create views of our 1D arrays to stack them.
     let views: Vec<_> = encoded_vectors.iter().map(|a| a.view()).collect();
     let data_matrix = ndarray::stack(Axis(0), &views)
-        .expect("Failed to stack arrays; this should not happen if all vectors have the same length.");
+        .map_err(|e| Error::Clustering(format!("failed to stack deck vectors: {}", e)))?;
 
     // e. Return the matrix and the corresponding deck hashes
     Ok((data_matrix, deck_hashes))
@@ -68,8 +69,10 @@ pub fn prepare_data_for_clustering(db_path: &str) -> Result<(Array2<f64>, Vec<St
 /// * `k` - The number of clusters to form.
 ///
 /// # Returns
-/// A 1D array containing the cluster assignments for each data point.
-pub fn run_kmeans(data: &Array2<f64>, k: usize) -> Array1<usize> {
+/// A 1D array containing the cluster assignments for each data point, or
+/// `Error::Clustering` if fitting the model fails (e.g. `k` exceeds the
+/// number of samples).
+pub fn run_kmeans(data: &Array2<f64>, k: usize) -> Result<Array1<usize>, Error> {
     use linfa_clustering::KMeans;
     use linfa::prelude::*;
     use rand_xoshiro::Xoshiro256Plus; // Use Xoshiro256Plus
@@ -81,7 +84,7 @@ pub fn run_kmeans(data: &Array2<f64>, k: usize) -> Array1<usize> {
     let rng = Xoshiro256Plus::seed_from_u64(42); // Seed Xoshiro256Plus
     let model = KMeans::params_with_rng(k, rng)
         .fit(&dataset) // Pass DatasetBase to fit
-        .expect("KMeans fitting failed");
+        .map_err(|e| Error::Clustering(e.to_string()))?;
 
-    model.predict(&dataset) // Pass DatasetBase to predict
+    Ok(model.predict(&dataset)) // Pass DatasetBase to predict
 }
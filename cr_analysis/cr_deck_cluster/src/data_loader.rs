@@ -1,4 +1,5 @@
-use rusqlite::{Connection, Result};
+use crate::error::Error;
+use rusqlite::Connection;
 use std::collections::HashMap;
 
 /// Loads all unique decks from the database and resolves their card instance hashes
@@ -11,7 +12,10 @@ use std::collections::HashMap;
 /// A `Result` containing a vector of tuples on success. Each tuple consists of:
 /// - `String`: The unique hash of a deck.
 /// - `Vec<u32>`: A vector of the 8 card type IDs that make up the deck.
-pub fn load_unique_decks(db_path: &str) -> Result<Vec<(String, Vec<u32>)>> {
+///
+/// Returns `Error::DataIntegrity` if a deck references a card instance hash
+/// that isn't present in `card_instances`.
+pub fn load_unique_decks(db_path: &str) -> Result<Vec<(String, Vec<u32>)>, Error> {
     // 1. Open a connection to the database.
     let conn = Connection::open(db_path)?;
 
@@ -44,10 +48,12 @@ pub fn load_unique_decks(db_path: &str) -> Result<Vec<(String, Vec<u32>)>> {
         for i in 1..=8 {
             let instance_hash: String = row.get(i)?;
             // Use the map to find the corresponding card_type_id.
-            // .expect() is used here assuming data integrity; an error would indicate
-            // a deck references a card instance that doesn't exist.
-            let card_id = instance_to_card_id_map.get(&instance_hash)
-                .expect("Data integrity error: deck references a non-existent card instance.");
+            let card_id = instance_to_card_id_map.get(&instance_hash).ok_or_else(|| {
+                Error::DataIntegrity {
+                    deck_hash: deck_hash.clone(),
+                    instance_hash: instance_hash.clone(),
+                }
+            })?;
             card_ids.push(*card_id);
         }
 
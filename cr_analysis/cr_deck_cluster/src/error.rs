@@ -0,0 +1,19 @@
+/// Crate-level error type for `cr_deck_cluster`, replacing the panics and
+/// raw `rusqlite::Error` that used to surface from `data_loader` and
+/// `clustering`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Db(#[from] rusqlite::Error),
+
+    #[error(
+        "data integrity error: deck {deck_hash} references card instance {instance_hash}, which does not exist"
+    )]
+    DataIntegrity {
+        deck_hash: String,
+        instance_hash: String,
+    },
+
+    #[error("clustering error: {0}")]
+    Clustering(String),
+}
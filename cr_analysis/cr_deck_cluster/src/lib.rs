@@ -4,6 +4,8 @@ use pyo3::prelude::*;
 pub mod data_loader;
 // Declare the new clustering module.
 pub mod clustering;
+// Declare the crate-level error type shared by data_loader and clustering.
+pub mod error;
 
 use pyo3::types::PyDict;
 use std::collections::HashMap;
@@ -13,7 +15,7 @@ use std::collections::HashMap;
 fn cluster_decks(py: Python, db_path: String, k: usize) -> PyResult<PyObject> {
     // 1. Prepare data for clustering
     let (feature_matrix, deck_hashes) = clustering::prepare_data_for_clustering(&db_path)
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to prepare data: {}", e)))?;
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
 
     if feature_matrix.is_empty() || deck_hashes.is_empty() {
         // Return an empty dictionary if there's no data to cluster
@@ -21,7 +23,8 @@ fn cluster_decks(py: Python, db_path: String, k: usize) -> PyResult<PyObject> {
     }
 
     // 2. Run K-Means clustering
-    let cluster_assignments = clustering::run_kmeans(&feature_matrix, k);
+    let cluster_assignments = clustering::run_kmeans(&feature_matrix, k)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
 
     // 3. Create a HashMap to map deck_hash to cluster_id
     let mut deck_to_cluster: HashMap<String, usize> = HashMap::new();
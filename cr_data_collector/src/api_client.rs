@@ -1,23 +1,45 @@
 use crate::api_models::BattleLog;
+use crate::error::Error;
+use rand::Rng;
+use std::time::Duration;
+use tracing::warn;
 
 const API_BASE_URL: &str = "https://api.clashroyale.com/v1";
 
-/// Fetches the battle log for a given player tag from the Clash Royale API.
+/// Upper bound on the exponential backoff delay between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Fetches the battle log for a given player tag from the Clash Royale API,
+/// retrying transient failures with exponential backoff.
+///
+/// A `429 Too Many Requests` response honors the `Retry-After` header when
+/// present, falling back to the capped exponential backoff otherwise. A
+/// `5xx` response always uses the capped exponential backoff. Any other
+/// non-2xx status (a `4xx` other than 429) fails immediately, since retrying
+/// it wouldn't help. The delay is applied with `tokio::time::sleep`, so it
+/// counts against the caller's concurrent request budget rather than
+/// blocking other in-flight tags.
 ///
 /// # Arguments
 ///
 /// * `client` - A shared reference to a `reqwest::Client`.
 /// * `api_key` - The API key for authorization.
 /// * `player_tag` - The player tag, which may include a leading '#'.
+/// * `max_retries` - Maximum number of retry attempts after the initial request.
+/// * `base_delay` - Base delay for the exponential backoff.
 ///
 /// # Returns
 ///
-/// A `Result` containing either the `BattleLog` on success or a `reqwest::Error` on failure.
+/// A `Result` containing either the `BattleLog` on success, `Error::Http` for
+/// a non-retryable status, or `Error::RetriesExhausted` once `max_retries` is
+/// used up.
 pub async fn fetch_battle_log(
     client: &reqwest::Client,
     api_key: &str,
     player_tag: &str,
-) -> Result<BattleLog, reqwest::Error> {
+    max_retries: u32,
+    base_delay: Duration,
+) -> Result<BattleLog, Error> {
     // Player tags in the API URL must be URL-encoded.
     // The '#' character, in particular, must be replaced with '%23'.
     let encoded_player_tag = player_tag.replace('#', "%23");
@@ -27,21 +49,70 @@ pub async fn fetch_battle_log(
         API_BASE_URL, encoded_player_tag
     );
 
-    println!("Fetching data from: {}", request_url);
+    let mut attempt = 0;
+    loop {
+        println!("Fetching data from: {}", request_url);
+
+        let response = client
+            .get(&request_url)
+            .bearer_auth(api_key) // Add the "Authorization: Bearer <key>" header.
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            // Deserialize the JSON response body into our BattleLog struct.
+            return Ok(response.json::<BattleLog>().await?);
+        }
 
-    let response = client
-        .get(&request_url)
-        .bearer_auth(api_key) // Add the "Authorization: Bearer <key>" header.
-        .send()
-        .await?;
+        // 429 and 5xx are transient; any other 4xx is not worth retrying.
+        let retryable = status.as_u16() == 429 || status.is_server_error();
+        if !retryable {
+            return Err(response.error_for_status().unwrap_err().into());
+        }
 
-    // Check if the request was successful (e.g., status code 200 OK).
-    // If not, this will return an Err variant with the status code.
-    let response = response.error_for_status()?;
+        if attempt >= max_retries {
+            return Err(Error::RetriesExhausted {
+                tag: player_tag.to_string(),
+                attempts: attempt,
+            });
+        }
 
-    // Deserialize the JSON response body into our BattleLog struct.
-    let battle_log = response.json::<BattleLog>().await?;
+        let delay = if status.as_u16() == 429 {
+            retry_after_delay(&response).unwrap_or_else(|| backoff_delay(base_delay, attempt))
+        } else {
+            backoff_delay(base_delay, attempt)
+        };
 
-    Ok(battle_log)
+        warn!(
+            "Retrying tag {} after {:?} (attempt {}/{}, status {})",
+            player_tag,
+            delay,
+            attempt + 1,
+            max_retries,
+            status
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
 }
 
+/// Parses the `Retry-After` header (in seconds) off a 429 response, if present.
+fn retry_after_delay(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Computes a capped exponential backoff delay with jitter for retry `attempt`
+/// (0-indexed), so concurrent tasks retrying around the same time don't all
+/// wake up at once.
+fn backoff_delay(base_delay: Duration, attempt: u32) -> Duration {
+    let exponential = base_delay.saturating_mul(1u32 << attempt.min(10));
+    let capped = exponential.min(MAX_BACKOFF);
+    let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+    capped.mul_f64(jitter)
+}
@@ -1,10 +1,13 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// A type alias for a collection of battles, representing the top-level API response.
 pub type BattleLog = Vec<Battle>;
 
 /// Represents a single battle from the Clash Royale API.
-#[derive(Debug, Deserialize)]
+///
+/// Also implements `Serialize` so battles round-trip through the `bulk-load`
+/// and `export` subcommands as newline-delimited JSON.
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Battle {
     /// The timestamp of when the battle took place.
@@ -18,7 +21,7 @@ pub struct Battle {
 }
 
 /// Represents information about a player in a battle.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayerInfo {
     /// The player's unique tag.
@@ -38,7 +41,7 @@ pub struct PlayerInfo {
 }
 
 /// Represents a single card used by a player.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Card {
     /// The name of the card.
@@ -0,0 +1,206 @@
+use crate::api_models::{Battle, Card, PlayerInfo};
+use crate::db;
+use deadpool_sqlite::rusqlite::{Connection as RusqliteConnection, Result as RusqliteResult};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+
+/// Number of parsed battles buffered between the parser and writer threads,
+/// and the number of battles committed per transaction on the writer side.
+const COMMIT_BATCH_SIZE: usize = 2000;
+
+/// Outcome of a `bulk-load` run.
+pub struct BulkLoadSummary {
+    pub parsed: usize,
+    pub inserted: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub malformed: usize,
+}
+
+/// Streams newline-delimited `Battle` JSON records from stdin into the
+/// database, so a dataset can be seeded or restored without hitting the
+/// Clash Royale API.
+///
+/// This thread parses each line and pushes the resulting `Battle` onto a
+/// bounded `mpsc` channel; a dedicated writer thread drains the channel and
+/// commits in batches of `COMMIT_BATCH_SIZE` using the same transaction
+/// logic (`db::insert_battle_log`) as the live collector. Malformed lines
+/// are logged and skipped rather than aborting the run, so a partial dump
+/// still loads.
+pub fn run_bulk_load(database_url: &str) -> Result<BulkLoadSummary, Box<dyn Error>> {
+    let (sender, receiver) = mpsc::sync_channel::<Battle>(COMMIT_BATCH_SIZE);
+
+    let database_url = database_url.to_string();
+    let writer = thread::spawn(move || -> RusqliteResult<db::InsertStats> {
+        let mut conn = RusqliteConnection::open(&database_url)?;
+        db::run_migrations(&mut conn)?;
+
+        let mut stats = db::InsertStats::default();
+        let mut batch = Vec::with_capacity(COMMIT_BATCH_SIZE);
+        for battle in receiver {
+            batch.push(battle);
+            if batch.len() >= COMMIT_BATCH_SIZE {
+                stats += commit_batch(&mut conn, &mut batch)?;
+            }
+        }
+        if !batch.is_empty() {
+            stats += commit_batch(&mut conn, &mut batch)?;
+        }
+        Ok(stats)
+    });
+
+    let stdin = io::stdin();
+    let mut parsed = 0;
+    let mut malformed = 0;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Battle>(&line) {
+            Ok(battle) => {
+                parsed += 1;
+                if sender.send(battle).is_err() {
+                    // Writer thread has died; stop feeding it and surface its error below.
+                    break;
+                }
+            }
+            Err(e) => {
+                malformed += 1;
+                eprintln!("Skipping malformed line: {}", e);
+            }
+        }
+    }
+    drop(sender);
+
+    let stats = writer
+        .join()
+        .map_err(|_| "bulk-load writer thread panicked")??;
+
+    Ok(BulkLoadSummary {
+        parsed,
+        inserted: stats.inserted,
+        duplicates: stats.duplicates,
+        invalid: stats.invalid,
+        malformed,
+    })
+}
+
+/// Commits a batch of battles in a single transaction and clears it, reusing
+/// the schema logic shared with the live collector.
+fn commit_batch(conn: &mut RusqliteConnection, batch: &mut Vec<Battle>) -> RusqliteResult<db::InsertStats> {
+    let tx = conn.transaction()?;
+    let stats = db::insert_battle_log(&tx, batch)?;
+    tx.commit()?;
+    batch.clear();
+    Ok(stats)
+}
+
+/// Streams every battle in the database back out to stdout as
+/// newline-delimited `Battle` JSON, the inverse of `run_bulk_load`.
+///
+/// Player names aren't persisted by the normalized schema, so exported
+/// battles carry an empty `name` for each player. Tags, crowns, trophies and
+/// the 8 cards of each deck come back, but a deck's cards are ordered by
+/// their instance hash rather than in their original order, and a card
+/// instance keeps the `evolution_level` and `elixir_cost` first stored for it.
+pub fn run_export(database_url: &str) -> Result<usize, Box<dyn Error>> {
+    let conn = RusqliteConnection::open(database_url)?;
+    let card_instances = load_card_instances(&conn)?;
+    let deck_cards = load_deck_cards(&conn, &card_instances)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT battle_time,
+                player_a_tag, player_a_crowns, player_a_deck_hash, player_a_starting_trophies, player_a_trophy_change, player_a_king_tower_hit_points,
+                player_b_tag, player_b_crowns, player_b_deck_hash, player_b_starting_trophies, player_b_trophy_change, player_b_king_tower_hit_points
+         FROM battles",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut exported = 0;
+    while let Some(row) = rows.next()? {
+        let player_a_deck_hash: String = row.get(3)?;
+        let player_b_deck_hash: String = row.get(9)?;
+        let battle = Battle {
+            battle_time: row.get(0)?,
+            team: vec![PlayerInfo {
+                tag: row.get(1)?,
+                name: String::new(),
+                crowns: row.get(2)?,
+                cards: deck_cards.get(&player_a_deck_hash).cloned().unwrap_or_default(),
+                starting_trophies: row.get(4)?,
+                trophy_change: row.get(5)?,
+                king_tower_hit_points: row.get(6)?,
+            }],
+            opponent: vec![PlayerInfo {
+                tag: row.get(7)?,
+                name: String::new(),
+                crowns: row.get(8)?,
+                cards: deck_cards.get(&player_b_deck_hash).cloned().unwrap_or_default(),
+                starting_trophies: row.get(10)?,
+                trophy_change: row.get(11)?,
+                king_tower_hit_points: row.get(12)?,
+            }],
+        };
+        writeln!(out, "{}", serde_json::to_string(&battle)?)?;
+        exported += 1;
+    }
+
+    Ok(exported)
+}
+
+/// Loads every card instance, keyed by `instance_hash`, joined with its
+/// static metadata so the original `Card` can be reconstructed.
+fn load_card_instances(conn: &RusqliteConnection) -> RusqliteResult<HashMap<String, Card>> {
+    let mut stmt = conn.prepare(
+        "SELECT ci.instance_hash, cm.name, cm.id, ci.level, ci.evolution_level, cm.elixir_cost
+         FROM card_instances ci
+         JOIN card_metadata cm ON ci.card_type_id = cm.id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let instance_hash: String = row.get(0)?;
+        let card = Card {
+            name: row.get(1)?,
+            id: row.get(2)?,
+            level: row.get(3)?,
+            evolution_level: row.get(4)?,
+            elixir_cost: row.get(5)?,
+        };
+        Ok((instance_hash, card))
+    })?;
+
+    let mut map = HashMap::new();
+    for row in rows {
+        let (instance_hash, card) = row?;
+        map.insert(instance_hash, card);
+    }
+    Ok(map)
+}
+
+/// Loads every deck, keyed by `deck_hash`, as its 8 constituent `Card`s.
+fn load_deck_cards(
+    conn: &RusqliteConnection,
+    card_instances: &HashMap<String, Card>,
+) -> RusqliteResult<HashMap<String, Vec<Card>>> {
+    let mut stmt = conn.prepare("SELECT * FROM decks")?;
+    let mut rows = stmt.query([])?;
+
+    let mut map = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let deck_hash: String = row.get(0)?;
+        let mut cards = Vec::with_capacity(8);
+        for i in 1..=8 {
+            let instance_hash: String = row.get(i)?;
+            if let Some(card) = card_instances.get(&instance_hash) {
+                cards.push(card.clone());
+            }
+        }
+        map.insert(deck_hash, cards);
+    }
+    Ok(map)
+}
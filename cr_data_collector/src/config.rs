@@ -1,34 +1,82 @@
+use crate::error::Error;
 use std::env;
 
+/// Default bind address for the Prometheus `/metrics` HTTP endpoint.
+const DEFAULT_METRICS_BIND_ADDR: &str = "0.0.0.0:9898";
+
+/// Default maximum number of retries for a transient API failure.
+const DEFAULT_MAX_RETRIES: u32 = 5;
+
+/// Default base delay (in milliseconds) for the retry backoff.
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 500;
+
 /// Holds the application's configuration values.
 pub struct Config {
     pub api_key: String,
     pub database_url: String,
+    /// Optional Redis connection URL (e.g. `redis://127.0.0.1/`). When set, the
+    /// collector uses a Redis-backed crawl frontier instead of the in-memory
+    /// queue/visited set, so progress survives restarts and can be shared by
+    /// multiple collector processes. Unset by default.
+    pub redis_url: Option<String>,
+    /// Bind address for the Prometheus `/metrics` HTTP endpoint. Configurable
+    /// via `METRICS_BIND_ADDR`; defaults to `0.0.0.0:9898` when unset.
+    pub metrics_bind_addr: String,
+    /// Maximum number of retries for a transient (429/5xx) API failure.
+    /// Configurable via `MAX_RETRIES`; defaults to 5 when unset.
+    pub max_retries: u32,
+    /// Base delay, in milliseconds, for the exponential retry backoff.
+    /// Configurable via `RETRY_BASE_DELAY_MS`; defaults to 500 when unset.
+    pub retry_base_delay_ms: u64,
+}
+
+/// Reads a required environment variable, returning `Error::MissingEnvVar`
+/// instead of panicking when it's unset. Shared by `Config::from_env` and the
+/// one-shot subcommands (`bulk-load`, `export`) that only need a single
+/// variable and so don't go through the full `Config`.
+pub fn required_env_var(name: &str) -> Result<String, Error> {
+    env::var(name).map_err(|_| Error::MissingEnvVar(name.to_string()))
 }
 
 impl Config {
     /// Creates a new Config instance by loading values from environment variables.
     ///
     /// This function will load variables from a .env file if it exists in the
-    /// project root. It will panic if any of the required environment variables
-    /// are not set.
-    pub fn from_env() -> Self {
+    /// project root. Returns `Error::MissingEnvVar` if a required variable is
+    /// not set.
+    pub fn from_env() -> Result<Self, Error> {
         // Load environment variables from the .env file.
         // .ok() silently ignores errors, which is fine if the file doesn't exist.
         dotenvy::dotenv().ok();
 
-        // Load CLASH_ROYALE_API_KEY, panicking if it's not set.
-        let api_key = env::var("CLASH_ROYALE_API_KEY")
-            .expect("CLASH_ROYALE_API_KEY must be set in your .env file");
+        let api_key = required_env_var("CLASH_ROYALE_API_KEY")?;
+
+        let database_url = required_env_var("DATABASE_URL")?;
+
+        // REDIS_URL is optional; its absence just means the in-memory frontier is used.
+        let redis_url = env::var("REDIS_URL").ok();
+
+        let metrics_bind_addr = env::var("METRICS_BIND_ADDR")
+            .unwrap_or_else(|_| DEFAULT_METRICS_BIND_ADDR.to_string());
+
+        let max_retries = env::var("MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RETRIES);
 
-        // Load DATABASE_URL, panicking if it's not set.
-        let database_url = env::var("DATABASE_URL")
-            .expect("DATABASE_URL must be set in your .env file");
+        let retry_base_delay_ms = env::var("RETRY_BASE_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RETRY_BASE_DELAY_MS);
 
-        Config {
+        Ok(Config {
             api_key,
             database_url,
-        }
+            redis_url,
+            metrics_bind_addr,
+            max_retries,
+            retry_base_delay_ms,
+        })
     }
 }
 
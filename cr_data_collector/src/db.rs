@@ -1,18 +1,23 @@
-use crate::api_models::{BattleLog, Card};
-use deadpool_sqlite::rusqlite::{params, Connection as RusqliteConnection, Result as RusqliteResult};
+use crate::api_models::{Battle, BattleLog, Card};
+use deadpool_sqlite::rusqlite::{
+    params, Connection as RusqliteConnection, Result as RusqliteResult, Transaction,
+};
 use deadpool_sqlite::Connection as DeadpoolConnection;
 use sha2::{Digest, Sha256};
 
-/// Initializes the application's database with a normalized schema.
-///
-/// This function creates the four necessary tables for storing card metadata, unique
-/// card instances, unique decks, and battle logs.
-///
-/// NOTE: If run against an existing database with the old schema, this will not
-/// perform a migration. Delete the old .db file to apply these schema changes.
-pub fn initialize_database(conn: &mut RusqliteConnection) -> RusqliteResult<()> {
-    let tx = conn.transaction()?;
+/// Current schema version. Bump this and append a new `fn(&Transaction)` to
+/// `MIGRATIONS` whenever the schema changes; each migration runs exactly
+/// once, in its own transaction, in order.
+pub const DB_VERSION: i64 = 2;
 
+/// Ordered schema migrations, indexed by the `user_version` they upgrade
+/// *to* — `MIGRATIONS[0]` takes a fresh database to version 1, `MIGRATIONS[1]`
+/// takes version 1 to version 2, and so on.
+const MIGRATIONS: &[fn(&Transaction) -> RusqliteResult<()>] = &[migration_v1, migration_v2];
+
+/// V1: the normalized schema (card_metadata, card_instances, decks, battles),
+/// replacing the older flat `cards` table.
+fn migration_v1(tx: &Transaction) -> RusqliteResult<()> {
     // -- Create card_metadata table for static card info --
     tx.execute(
         "CREATE TABLE IF NOT EXISTS card_metadata (
@@ -78,10 +83,57 @@ pub fn initialize_database(conn: &mut RusqliteConnection) -> RusqliteResult<()>
         [],
     )?;
 
-    // Drop the old, now-redundant `cards` table if it exists
+    // Drop the old, now-redundant `cards` table if it exists.
     tx.execute("DROP TABLE IF EXISTS cards", [])?;
 
-    tx.commit()
+    Ok(())
+}
+
+/// V2: a uniqueness constraint on the battle's natural key, so re-running
+/// `bulk-load` on the same dump (or loading an `export` back into a
+/// populated database) skips battles already present instead of inserting
+/// duplicate rows.
+fn migration_v2(tx: &Transaction) -> RusqliteResult<()> {
+    tx.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_battles_natural_key
+         ON battles (battle_time, player_a_tag, player_b_tag)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Reads the schema version currently recorded in the database via
+/// `PRAGMA user_version`.
+pub fn curr_db_version(conn: &RusqliteConnection) -> RusqliteResult<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Brings the database up to `DB_VERSION`, applying each pending migration
+/// in its own transaction and bumping `user_version` inside that same
+/// transaction, so an interrupted upgrade resumes cleanly on the next
+/// startup instead of re-running migrations that already landed.
+///
+/// Panics if the on-disk version is newer than this binary's `DB_VERSION`,
+/// since there's no way to safely run an older binary against a newer schema.
+pub fn run_migrations(conn: &mut RusqliteConnection) -> RusqliteResult<()> {
+    let current = curr_db_version(conn)?;
+
+    if current > DB_VERSION {
+        panic!(
+            "database schema version ({current}) is newer than this binary supports (DB_VERSION = {DB_VERSION})"
+        );
+    }
+
+    for version in (current + 1)..=DB_VERSION {
+        let migration = MIGRATIONS[(version - 1) as usize];
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
 }
 
 /// Creates a unique hash for a specific card instance (type, level, and evolution).
@@ -103,88 +155,123 @@ fn calculate_deck_hash(card_instance_hashes: &mut [String]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-/// Saves a battle log and its associated card data to the normalized database schema.
-pub async fn save_battle_log(
-    conn: &DeadpoolConnection,
-    battle_log: BattleLog,
-) -> Result<RusqliteResult<usize>, deadpool_sqlite::InteractError> {
-    conn.interact(move |conn| {
-        let tx = conn.transaction()?;
-        let mut new_battles_count = 0;
-
-        for battle in &battle_log {
-            // Ensure we only process 1v1 battles with full 8-card decks.
-            if battle.team.len() != 1 || battle.opponent.len() != 1
-                || battle.team[0].cards.len() != 8 || battle.opponent[0].cards.len() != 8 {
-                continue;
-            }
-
-            // -- Step 1: Process all 16 cards to save their metadata and instances --
-            let all_cards = battle.team[0].cards.iter().chain(battle.opponent[0].cards.iter());
-            for card in all_cards {
-                // Save static card info (name, elixir). `OR IGNORE` is efficient.
-                tx.execute(
-                    "INSERT OR IGNORE INTO card_metadata (id, name, elixir_cost) VALUES (?1, ?2, ?3)",
-                    params![card.id, card.name, card.elixir_cost],
-                )?;
-                // Save unique card instance (id + level + evolution).
-                tx.execute(
-                    "INSERT OR IGNORE INTO card_instances (instance_hash, card_type_id, level, evolution_level) VALUES (?1, ?2, ?3, ?4)",
-                    params![
-                        calculate_card_instance_hash(card),
-                        card.id,
-                        card.level,
-                        card.evolution_level
-                    ],
-                )?;
-            }
-
-            // -- Step 2: Process Player A's deck --
-            let player_a = &battle.team[0];
-            let mut player_a_instance_hashes: Vec<String> = player_a.cards.iter().map(calculate_card_instance_hash).collect();
-            let player_a_deck_hash = calculate_deck_hash(&mut player_a_instance_hashes);
+/// Outcome of inserting a batch of battles: how many were genuinely new,
+/// how many were already present (by natural key), and how many didn't pass
+/// the 1v1/8-card sanity check and were never attempted.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InsertStats {
+    pub inserted: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+impl std::ops::AddAssign for InsertStats {
+    fn add_assign(&mut self, other: Self) {
+        self.inserted += other.inserted;
+        self.duplicates += other.duplicates;
+        self.invalid += other.invalid;
+    }
+}
+
+/// Inserts a batch of battles and their associated card data into an
+/// already-open transaction, returning counts of new, duplicate, and invalid
+/// battles. Shared by the live collector's `save_battle_log` and the
+/// `bulk-load` importer so both paths use exactly the same schema logic.
+pub fn insert_battle_log(tx: &Transaction, battle_log: &[Battle]) -> RusqliteResult<InsertStats> {
+    let mut stats = InsertStats::default();
+
+    for battle in battle_log {
+        // Ensure we only process 1v1 battles with full 8-card decks.
+        if battle.team.len() != 1 || battle.opponent.len() != 1
+            || battle.team[0].cards.len() != 8 || battle.opponent[0].cards.len() != 8 {
+            stats.invalid += 1;
+            continue;
+        }
+
+        // -- Step 1: Process all 16 cards to save their metadata and instances --
+        let all_cards = battle.team[0].cards.iter().chain(battle.opponent[0].cards.iter());
+        for card in all_cards {
+            // Save static card info (name, elixir). `OR IGNORE` is efficient.
             tx.execute(
-                "INSERT OR IGNORE INTO decks (deck_hash, card_instance_hash_1, card_instance_hash_2, card_instance_hash_3, card_instance_hash_4, card_instance_hash_5, card_instance_hash_6, card_instance_hash_7, card_instance_hash_8)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    player_a_deck_hash,
-                    player_a_instance_hashes[0], player_a_instance_hashes[1], player_a_instance_hashes[2], player_a_instance_hashes[3],
-                    player_a_instance_hashes[4], player_a_instance_hashes[5], player_a_instance_hashes[6], player_a_instance_hashes[7],
-                ],
+                "INSERT OR IGNORE INTO card_metadata (id, name, elixir_cost) VALUES (?1, ?2, ?3)",
+                params![card.id, card.name, card.elixir_cost],
             )?;
-
-            // -- Step 3: Process Player B's deck --
-            let player_b = &battle.opponent[0];
-            let mut player_b_instance_hashes: Vec<String> = player_b.cards.iter().map(calculate_card_instance_hash).collect();
-            let player_b_deck_hash = calculate_deck_hash(&mut player_b_instance_hashes);
+            // Save unique card instance (id + level + evolution).
             tx.execute(
-                "INSERT OR IGNORE INTO decks (deck_hash, card_instance_hash_1, card_instance_hash_2, card_instance_hash_3, card_instance_hash_4, card_instance_hash_5, card_instance_hash_6, card_instance_hash_7, card_instance_hash_8)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                "INSERT OR IGNORE INTO card_instances (instance_hash, card_type_id, level, evolution_level) VALUES (?1, ?2, ?3, ?4)",
                 params![
-                    player_b_deck_hash,
-                    player_b_instance_hashes[0], player_b_instance_hashes[1], player_b_instance_hashes[2], player_b_instance_hashes[3],
-                    player_b_instance_hashes[4], player_b_instance_hashes[5], player_b_instance_hashes[6], player_b_instance_hashes[7],
+                    calculate_card_instance_hash(card),
+                    card.id,
+                    card.level,
+                    card.evolution_level
                 ],
             )?;
+        }
 
-            // -- Step 4: Insert the battle record with new optional fields --
-            let changes = tx.execute(
-                "INSERT INTO battles (
-                    battle_time,
-                    player_a_tag, player_a_crowns, player_a_deck_hash, player_a_starting_trophies, player_a_trophy_change, player_a_king_tower_hit_points,
-                    player_b_tag, player_b_crowns, player_b_deck_hash, player_b_starting_trophies, player_b_trophy_change, player_b_king_tower_hit_points
-                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-                params![
-                    battle.battle_time,
-                    player_a.tag, player_a.crowns, player_a_deck_hash, player_a.starting_trophies, player_a.trophy_change, player_a.king_tower_hit_points,
-                    player_b.tag, player_b.crowns, player_b_deck_hash, player_b.starting_trophies, player_b.trophy_change, player_b.king_tower_hit_points,
-                ],
-            )?;
-            new_battles_count += changes;
+        // -- Step 2: Process Player A's deck --
+        let player_a = &battle.team[0];
+        let mut player_a_instance_hashes: Vec<String> = player_a.cards.iter().map(calculate_card_instance_hash).collect();
+        let player_a_deck_hash = calculate_deck_hash(&mut player_a_instance_hashes);
+        tx.execute(
+            "INSERT OR IGNORE INTO decks (deck_hash, card_instance_hash_1, card_instance_hash_2, card_instance_hash_3, card_instance_hash_4, card_instance_hash_5, card_instance_hash_6, card_instance_hash_7, card_instance_hash_8)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                player_a_deck_hash,
+                player_a_instance_hashes[0], player_a_instance_hashes[1], player_a_instance_hashes[2], player_a_instance_hashes[3],
+                player_a_instance_hashes[4], player_a_instance_hashes[5], player_a_instance_hashes[6], player_a_instance_hashes[7],
+            ],
+        )?;
+
+        // -- Step 3: Process Player B's deck --
+        let player_b = &battle.opponent[0];
+        let mut player_b_instance_hashes: Vec<String> = player_b.cards.iter().map(calculate_card_instance_hash).collect();
+        let player_b_deck_hash = calculate_deck_hash(&mut player_b_instance_hashes);
+        tx.execute(
+            "INSERT OR IGNORE INTO decks (deck_hash, card_instance_hash_1, card_instance_hash_2, card_instance_hash_3, card_instance_hash_4, card_instance_hash_5, card_instance_hash_6, card_instance_hash_7, card_instance_hash_8)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                player_b_deck_hash,
+                player_b_instance_hashes[0], player_b_instance_hashes[1], player_b_instance_hashes[2], player_b_instance_hashes[3],
+                player_b_instance_hashes[4], player_b_instance_hashes[5], player_b_instance_hashes[6], player_b_instance_hashes[7],
+            ],
+        )?;
+
+        // -- Step 4: Insert the battle record with new optional fields --
+        // `OR IGNORE` relies on `idx_battles_natural_key`
+        // (battle_time, player_a_tag, player_b_tag) to silently no-op a
+        // re-import of a battle already on file instead of duplicating it.
+        let changes = tx.execute(
+            "INSERT OR IGNORE INTO battles (
+                battle_time,
+                player_a_tag, player_a_crowns, player_a_deck_hash, player_a_starting_trophies, player_a_trophy_change, player_a_king_tower_hit_points,
+                player_b_tag, player_b_crowns, player_b_deck_hash, player_b_starting_trophies, player_b_trophy_change, player_b_king_tower_hit_points
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                battle.battle_time,
+                player_a.tag, player_a.crowns, player_a_deck_hash, player_a.starting_trophies, player_a.trophy_change, player_a.king_tower_hit_points,
+                player_b.tag, player_b.crowns, player_b_deck_hash, player_b.starting_trophies, player_b.trophy_change, player_b.king_tower_hit_points,
+            ],
+        )?;
+        if changes == 1 {
+            stats.inserted += 1;
+        } else {
+            stats.duplicates += 1;
         }
+    }
+
+    Ok(stats)
+}
 
+/// Saves a battle log and its associated card data to the normalized database schema.
+pub async fn save_battle_log(
+    conn: &DeadpoolConnection,
+    battle_log: BattleLog,
+) -> Result<RusqliteResult<InsertStats>, deadpool_sqlite::InteractError> {
+    conn.interact(move |conn| {
+        let tx = conn.transaction()?;
+        let stats = insert_battle_log(&tx, &battle_log)?;
         tx.commit()?;
-        Ok(new_battles_count)
+        Ok(stats)
     })
         .await
 }
@@ -0,0 +1,13 @@
+/// Crate-level error type for the data collector, replacing the panics and
+/// raw `reqwest::Error` that used to surface from `Config` and `api_client`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("missing required environment variable: {0}")]
+    MissingEnvVar(String),
+
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("exhausted {attempts} retries fetching battle log for tag {tag}")]
+    RetriesExhausted { tag: String, attempts: u32 },
+}
@@ -0,0 +1,132 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Redis key for the pending-tags queue (a Redis list).
+const QUEUE_KEY: &str = "cr_collector:tags_to_process";
+/// Redis key for the discovered-tags set (a Redis set).
+const VISITED_KEY: &str = "cr_collector:processed_tags";
+
+/// Error returned by frontier operations.
+#[derive(Debug)]
+pub enum FrontierError {
+    Redis(redis::RedisError),
+}
+
+impl std::fmt::Display for FrontierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrontierError::Redis(e) => write!(f, "Redis frontier error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FrontierError {}
+
+impl From<redis::RedisError> for FrontierError {
+    fn from(e: redis::RedisError) -> Self {
+        FrontierError::Redis(e)
+    }
+}
+
+/// The crawl frontier: a queue of tags waiting to be processed plus the set
+/// of tags already discovered.
+///
+/// The in-memory variant is the default and keeps everything in process
+/// memory, exactly as before. When `Config::redis_url` is set, `connect`
+/// returns the Redis-backed variant instead, storing the queue as a Redis
+/// list and the discovered set as a Redis set so progress survives restarts
+/// and multiple collector instances can share work.
+pub enum Frontier {
+    InMemory {
+        queue: Arc<Mutex<VecDeque<String>>>,
+        visited: Arc<Mutex<HashSet<String>>>,
+    },
+    Redis {
+        // `MultiplexedConnection` is cheap to clone (it just clones a handle
+        // to the single shared connection task) and safe to use from many
+        // concurrent callers, so we establish it once in `connect` and clone
+        // it per operation instead of opening a fresh connection each time.
+        conn: redis::aio::MultiplexedConnection,
+    },
+}
+
+impl Frontier {
+    /// Connects to the backend selected by `redis_url`: Redis when present,
+    /// otherwise the in-memory default.
+    pub async fn connect(redis_url: Option<&str>) -> Result<Self, FrontierError> {
+        match redis_url {
+            Some(url) => {
+                let client = redis::Client::open(url)?;
+                let conn = client.get_multiplexed_async_connection().await?;
+                Ok(Frontier::Redis { conn })
+            }
+            None => Ok(Frontier::InMemory {
+                queue: Arc::new(Mutex::new(VecDeque::new())),
+                visited: Arc::new(Mutex::new(HashSet::new())),
+            }),
+        }
+    }
+
+    /// Atomically pops up to `count` tags off the front of the queue.
+    pub async fn dequeue_batch(&self, count: usize) -> Result<Vec<String>, FrontierError> {
+        match self {
+            Frontier::InMemory { queue, .. } => {
+                let mut guard = queue.lock().await;
+                let drain_count = std::cmp::min(count, guard.len());
+                Ok(guard.drain(..drain_count).collect())
+            }
+            Frontier::Redis { conn } => {
+                use redis::AsyncCommands;
+                let mut conn = conn.clone();
+                // LPOP with a count argument pops up to `count` elements in a
+                // single atomic round trip.
+                let tags: Vec<String> = conn.lpop(QUEUE_KEY, std::num::NonZeroUsize::new(count)).await?;
+                Ok(tags)
+            }
+        }
+    }
+
+    /// Marks `tag` as discovered and enqueues it if it hasn't been seen
+    /// before. Returns `true` if the tag was newly enqueued.
+    ///
+    /// For the Redis backend this is a single `SADD` whose integer reply
+    /// tells us whether the tag was new, followed by an `RPUSH` only when it
+    /// was — eliminating the separate-locks race the in-memory queue and
+    /// visited set used to have.
+    pub async fn enqueue_if_new(&self, tag: &str) -> Result<bool, FrontierError> {
+        match self {
+            Frontier::InMemory { queue, visited } => {
+                let mut visited_guard = visited.lock().await;
+                if !visited_guard.insert(tag.to_string()) {
+                    return Ok(false);
+                }
+                queue.lock().await.push_back(tag.to_string());
+                Ok(true)
+            }
+            Frontier::Redis { conn } => {
+                use redis::AsyncCommands;
+                let mut conn = conn.clone();
+                let added: i64 = conn.sadd(VISITED_KEY, tag).await?;
+                if added == 1 {
+                    let _: i64 = conn.rpush(QUEUE_KEY, tag).await?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+        }
+    }
+
+    /// Returns the current number of tags waiting in the queue.
+    pub async fn queue_len(&self) -> Result<usize, FrontierError> {
+        match self {
+            Frontier::InMemory { queue, .. } => Ok(queue.lock().await.len()),
+            Frontier::Redis { conn } => {
+                use redis::AsyncCommands;
+                let mut conn = conn.clone();
+                Ok(conn.llen(QUEUE_KEY).await?)
+            }
+        }
+    }
+}
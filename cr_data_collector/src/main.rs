@@ -1,8 +1,8 @@
-use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use futures::stream::StreamExt;
 use deadpool_sqlite::{Config as DeadpoolConfig, Runtime};
-use tokio::sync::Mutex;
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -10,7 +10,14 @@ use tracing_subscriber::FmtSubscriber;
 mod config;
 pub mod api_models;
 pub mod api_client;
+pub mod bulk;
 pub mod db;
+pub mod error;
+pub mod frontier;
+pub mod metrics;
+
+use frontier::Frontier;
+use metrics::Metrics;
 
 // --- Constants for the concurrent loop ---
 const BATCH_SIZE: usize = 50; // Number of tags to process from the queue in one go.
@@ -18,6 +25,16 @@ const CONCURRENT_REQUESTS: usize = 10; // Max number of API requests to have in
 
 #[tokio::main]
 async fn main() {
+    // `bulk-load` and `export` are one-shot subcommands that move Battle
+    // records between STDIN/STDOUT and the database, bypassing the live
+    // crawler entirely.
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("bulk-load") => return run_bulk_load_mode(),
+        Some("export") => return run_export_mode(),
+        _ => {}
+    }
+
     // Initialize Logging
     let subscriber = FmtSubscriber::builder().with_max_level(Level::INFO).finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
@@ -25,58 +42,92 @@ async fn main() {
     info!("🚀 Starting High-Concurrency Clash Royale Data Collector...");
 
     // --- Initialize clients and configuration ---
-    let config = config::Config::from_env();
+    let config = config::Config::from_env().expect("Invalid configuration");
     
     // Create a deadpool for SQLite connections using the correct API.
     let pool_cfg = DeadpoolConfig::new(&config.database_url);
     let pool = pool_cfg.create_pool(Runtime::Tokio1).expect("Failed to create pool.");
 
-    // Get an initial connection to set up the database schema.
+    // Get an initial connection to bring the database schema up to date.
     let conn = pool.get().await.expect("Failed to get initial db connection");
-    conn.interact(|conn| db::initialize_database(conn))
+    conn.interact(|conn| db::run_migrations(conn))
         .await
         .expect("Database interaction failed")
         .expect("Failed to initialize database schema");
     
     let http_client = Arc::new(reqwest::Client::new());
-    
-    info!("✅ Configuration and clients initialized successfully.");
 
-    // --- Set up shared state for concurrency ---
-    let tags_to_process = Arc::new(Mutex::new(VecDeque::new()));
-    let processed_tags = Arc::new(Mutex::new(HashSet::new()));
+    // --- Start the Prometheus metrics server ---
+    let metrics = Metrics::new();
+    let metrics_bind_addr: SocketAddr = config
+        .metrics_bind_addr
+        .parse()
+        .expect("Invalid METRICS_BIND_ADDR");
+    metrics::spawn_metrics_server(metrics.clone(), metrics_bind_addr);
+
+    info!("✅ Configuration and clients initialized successfully.");
 
-    // Seed the queue
-    {
-        let mut queue = tags_to_process.lock().await;
-        queue.push_back("#RVCQ2CQGJ".to_string());
-        queue.push_back("#VCQUY9Y8U".to_string());
+    // --- Set up the crawl frontier ---
+    // Redis-backed when `REDIS_URL` is configured, so the queue survives
+    // restarts and can be shared across collector instances; in-memory
+    // otherwise.
+    let frontier = Arc::new(
+        Frontier::connect(config.redis_url.as_deref())
+            .await
+            .expect("Failed to connect to crawl frontier"),
+    );
+    let processed_count = Arc::new(AtomicUsize::new(0));
+
+    // Seed the queue with initial tags.
+    for seed_tag in ["#RVCQ2CQGJ", "#VCQUY9Y8U"] {
+        frontier
+            .enqueue_if_new(seed_tag)
+            .await
+            .expect("Failed to seed frontier");
     }
     info!("Seeded queue with initial tags. Starting main processing loop...");
 
     // --- Main Concurrent Loop ---
     loop {
-        let mut batch_of_tags = Vec::with_capacity(BATCH_SIZE);
-        
-        // Lock the queue, drain a batch of tags, then immediately release the lock.
-        {
-            let mut queue_guard = tags_to_process.lock().await;
-            
-            // ** THE FIX IS HERE **
-            // 1. First, get the number of items to drain (immutable borrow).
-            let drain_count = std::cmp::min(BATCH_SIZE, queue_guard.len());
-            // 2. Then, use that number to drain (mutable borrow).
-            batch_of_tags.extend(queue_guard.drain(..drain_count));
+        let batch_of_tags = match frontier.dequeue_batch(BATCH_SIZE).await {
+            Ok(tags) => tags,
+            Err(e) => {
+                error!("   ❌ Frontier error dequeuing batch: {}", e);
+                metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+
+        match frontier.queue_len().await {
+            Ok(queue_depth) => {
+                metrics
+                    .queue_depth
+                    .store(queue_depth as i64, Ordering::Relaxed);
+            }
+            Err(e) => {
+                error!("   ❌ Frontier error reading queue length: {}", e);
+                metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
+            }
         }
 
         if batch_of_tags.is_empty() {
             info!("Queue is empty, waiting a moment to see if new tags appear...");
             tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-            
-            // If the queue is still empty after waiting, we can shut down.
-            if tags_to_process.lock().await.is_empty() {
-                info!("🏁 Queue is still empty. Shutting down.");
-                break;
+
+            // If the queue is still empty after waiting, we can shut down. A
+            // transient error here is treated as "not empty" so a Redis
+            // hiccup can't accidentally shut down the collector.
+            match frontier.queue_len().await {
+                Ok(0) => {
+                    info!("🏁 Queue is still empty. Shutting down.");
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!("   ❌ Frontier error reading queue length: {}", e);
+                    metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
+                }
             }
             continue;
         }
@@ -90,25 +141,35 @@ async fn main() {
                 let pool = pool.clone();
                 let http_client = http_client.clone();
                 let api_key = config.api_key.clone();
-                let tags_to_process = tags_to_process.clone();
-                let processed_tags = processed_tags.clone();
+                let frontier = frontier.clone();
+                let processed_count = processed_count.clone();
+                let metrics = metrics.clone();
+                let max_retries = config.max_retries;
+                let retry_base_delay = std::time::Duration::from_millis(config.retry_base_delay_ms);
 
                 async move {
-                    // Skip if another concurrent task has already processed this tag.
-                    if processed_tags.lock().await.contains(&tag) {
-                        return;
-                    }
-
                     info!("-> Fetching tag: {}", tag);
                     let db_conn = match pool.get().await {
                         Ok(conn) => conn,
                         Err(e) => {
                             error!("Failed to get DB connection from pool: {}", e);
+                            metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
                             return;
                         }
                     };
 
-                    match api_client::fetch_battle_log(&http_client, &api_key, &tag).await {
+                    metrics.in_flight_requests.fetch_add(1, Ordering::Relaxed);
+                    let fetch_result = api_client::fetch_battle_log(
+                        &http_client,
+                        &api_key,
+                        &tag,
+                        max_retries,
+                        retry_base_delay,
+                    )
+                    .await;
+                    metrics.in_flight_requests.fetch_sub(1, Ordering::Relaxed);
+
+                    match fetch_result {
                         Ok(battle_log) => {
                             // Discover new tags before saving.
                             let mut discovered_tags = Vec::new();
@@ -117,32 +178,69 @@ async fn main() {
                                     discovered_tags.push(opponent.tag.clone());
                                 }
                             }
-                            
+
                             // Save battles to DB, handling the nested Result.
                             match db::save_battle_log(&db_conn, battle_log).await {
-                                Ok(Ok(count)) => info!("   ✅ Saved {} new battles for tag {}", count, tag),
-                                Ok(Err(e)) => error!("   ❌ DB (rusqlite) Error for tag {}: {}", tag, e),
-                                Err(e) => error!("   ❌ DB (deadpool) Error for tag {}: {}", tag, e),
+                                Ok(Ok(stats)) => {
+                                    info!(
+                                        "   ✅ Saved {} new battles for tag {} ({} duplicates, {} invalid)",
+                                        stats.inserted, tag, stats.duplicates, stats.invalid
+                                    );
+                                    metrics.battles_saved_total.fetch_add(stats.inserted as u64, Ordering::Relaxed);
+                                }
+                                Ok(Err(e)) => {
+                                    error!("   ❌ DB (rusqlite) Error for tag {}: {}", tag, e);
+                                    metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Err(e) => {
+                                    error!("   ❌ DB (deadpool) Error for tag {}: {}", tag, e);
+                                    metrics.db_errors_total.fetch_add(1, Ordering::Relaxed);
+                                }
                             }
 
-                            // Add newly discovered tags to the shared queue.
-                            let mut queue_guard = tags_to_process.lock().await;
-                            let processed_guard = processed_tags.lock().await;
+                            // Add newly discovered tags to the frontier; the
+                            // frontier itself dedups against tags already seen.
                             for discovered_tag in discovered_tags {
-                                if !processed_guard.contains(&discovered_tag) && !queue_guard.contains(&discovered_tag) {
-                                    queue_guard.push_back(discovered_tag);
+                                if let Err(e) = frontier.enqueue_if_new(&discovered_tag).await {
+                                    error!("   ❌ Frontier error enqueuing {}: {}", discovered_tag, e);
                                 }
                             }
                         }
-                        Err(e) => error!("   ❌ API Error for tag {}: {}", tag, e),
+                        Err(e) => {
+                            error!("   ❌ API Error for tag {}: {}", tag, e);
+                            metrics.api_errors_total.fetch_add(1, Ordering::Relaxed);
+                        }
                     }
-                    
-                    // Mark tag as processed.
-                    processed_tags.lock().await.insert(tag);
+
+                    processed_count.fetch_add(1, Ordering::Relaxed);
+                    metrics.tags_processed_total.fetch_add(1, Ordering::Relaxed);
                 }
             })
             .await;
-        
-        info!("Finished processing batch. Processed tags count: {}", processed_tags.lock().await.len());
+
+        info!("Finished processing batch. Processed tags count: {}", processed_count.load(Ordering::Relaxed));
     }
 }
+
+/// Runs the `bulk-load` subcommand: reads DATABASE_URL and imports
+/// newline-delimited `Battle` JSON from stdin.
+fn run_bulk_load_mode() {
+    dotenvy::dotenv().ok();
+    let database_url = config::required_env_var("DATABASE_URL").expect("Invalid configuration");
+
+    let summary = bulk::run_bulk_load(&database_url).expect("bulk-load failed");
+    eprintln!(
+        "bulk-load complete: parsed={} inserted={} duplicates={} invalid={} malformed={}",
+        summary.parsed, summary.inserted, summary.duplicates, summary.invalid, summary.malformed
+    );
+}
+
+/// Runs the `export` subcommand: reads DATABASE_URL and writes every battle
+/// in the database to stdout as newline-delimited `Battle` JSON.
+fn run_export_mode() {
+    dotenvy::dotenv().ok();
+    let database_url = config::required_env_var("DATABASE_URL").expect("Invalid configuration");
+
+    let exported = bulk::run_export(&database_url).expect("export failed");
+    eprintln!("export complete: {} battles written to stdout", exported);
+}
@@ -0,0 +1,100 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use tracing::{error, info};
+
+/// Shared counters and gauges for the live collector, exposed at `/metrics`
+/// in Prometheus text exposition format so a long crawl can be scraped and
+/// alerted on instead of monitored by tailing logs.
+#[derive(Default)]
+pub struct Metrics {
+    pub tags_processed_total: AtomicU64,
+    pub queue_depth: AtomicI64,
+    pub in_flight_requests: AtomicI64,
+    pub battles_saved_total: AtomicU64,
+    pub api_errors_total: AtomicU64,
+    pub db_errors_total: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Renders the current values in Prometheus text exposition format.
+    fn render(&self) -> String {
+        format!(
+            "# HELP cr_collector_tags_processed_total Total tags processed by the collector.\n\
+             # TYPE cr_collector_tags_processed_total counter\n\
+             cr_collector_tags_processed_total {tags_processed}\n\
+             # HELP cr_collector_queue_depth Current number of tags waiting in the crawl frontier.\n\
+             # TYPE cr_collector_queue_depth gauge\n\
+             cr_collector_queue_depth {queue_depth}\n\
+             # HELP cr_collector_in_flight_requests Number of Clash Royale API requests currently in flight.\n\
+             # TYPE cr_collector_in_flight_requests gauge\n\
+             cr_collector_in_flight_requests {in_flight}\n\
+             # HELP cr_collector_battles_saved_total Total battles saved to the database.\n\
+             # TYPE cr_collector_battles_saved_total counter\n\
+             cr_collector_battles_saved_total {battles_saved}\n\
+             # HELP cr_collector_api_errors_total Total Clash Royale API errors encountered.\n\
+             # TYPE cr_collector_api_errors_total counter\n\
+             cr_collector_api_errors_total {api_errors}\n\
+             # HELP cr_collector_db_errors_total Total database errors encountered.\n\
+             # TYPE cr_collector_db_errors_total counter\n\
+             cr_collector_db_errors_total {db_errors}\n",
+            tags_processed = self.tags_processed_total.load(Ordering::Relaxed),
+            queue_depth = self.queue_depth.load(Ordering::Relaxed),
+            in_flight = self.in_flight_requests.load(Ordering::Relaxed),
+            battles_saved = self.battles_saved_total.load(Ordering::Relaxed),
+            api_errors = self.api_errors_total.load(Ordering::Relaxed),
+            db_errors = self.db_errors_total.load(Ordering::Relaxed),
+        )
+    }
+}
+
+async fn handle_request(
+    metrics: Arc<Metrics>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() == "/metrics" {
+        Ok(Response::new(Body::from(metrics.render())))
+    } else {
+        Ok(Response::builder()
+            .status(404)
+            .body(Body::from("not found"))
+            .unwrap())
+    }
+}
+
+/// Spawns the `/metrics` HTTP server as a background tokio task, bound to
+/// `bind_addr`. Runs for the lifetime of the process; a bind failure is
+/// logged rather than killing the crawl.
+pub fn spawn_metrics_server(metrics: Arc<Metrics>, bind_addr: SocketAddr) {
+    tokio::spawn(async move {
+        let make_svc = make_service_fn(move |_conn| {
+            let metrics = metrics.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle_request(metrics.clone(), req))) }
+        });
+
+        // `Server::bind` panics on a bind failure; `try_bind` surfaces it as a
+        // `Result` instead, so a port conflict (e.g. two collector instances
+        // defaulting to the same METRICS_BIND_ADDR) is logged like any other
+        // server error rather than taking down the task via a silent panic.
+        let server = match Server::try_bind(&bind_addr) {
+            Ok(builder) => builder.serve(make_svc),
+            Err(e) => {
+                error!("Failed to bind metrics server to {}: {}", bind_addr, e);
+                return;
+            }
+        };
+
+        info!("📈 Metrics server listening on http://{}/metrics", bind_addr);
+        if let Err(e) = server.await {
+            error!("Metrics server error: {}", e);
+        }
+    });
+}